@@ -0,0 +1,75 @@
+// Copyright 2012-2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Width measurement by extended grapheme cluster rather than by individual
+//! `char`.
+//!
+//! A cluster's width is the width of its first (base) character: combining
+//! marks contribute 0, a pair of regional indicators collapses to a single
+//! width-2 flag, and a zero-width-joiner sequence collapses to the width of
+//! its first emoji.
+
+use core::iter::Iterator;
+use core::option::Option::Some;
+
+use tables::charwidth as cw;
+
+/// Returns the displayed width of `s` in columns, measuring by extended
+/// grapheme cluster instead of summing per-`char` widths.
+pub fn width(s: &str, is_cjk: bool) -> usize {
+    let mut total = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if cw::is_combining_mark(c) {
+            // A combining mark with no preceding base contributes no width.
+            continue;
+        }
+
+        let w = if cw::is_regional_indicator(c) {
+            // A pair of regional indicators spells out a single flag
+            // cluster; a lone one is also rendered as width 2.
+            if let Some(&next) = chars.peek() {
+                if cw::is_regional_indicator(next) {
+                    chars.next();
+                }
+            }
+            2
+        } else {
+            // A variation selector immediately following the base changes
+            // its presentation (and so its width); use the same lookahead
+            // as the non-clustering `str_width`/`width_prefix_len`.
+            let next = chars.peek().cloned();
+            let (w, consumed_next) = super::char_width_with_next(c, next, is_cjk);
+            if consumed_next {
+                chars.next();
+            }
+            w
+        };
+
+        // Absorb the rest of the cluster: trailing combining marks add no
+        // further width, and each zero-width joiner glues in the following
+        // code point (which also adds no further width).
+        loop {
+            match chars.peek() {
+                Some(&cw::ZWJ) => {
+                    chars.next();
+                    chars.next();
+                }
+                Some(&next) if cw::is_combining_mark(next) => {
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+
+        total += w;
+    }
+    total
+}
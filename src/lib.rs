@@ -53,12 +53,68 @@ use tables::charwidth as cw;
 pub use tables::UNICODE_VERSION;
 
 mod tables;
+mod grapheme;
+
+/// Returns the width of `c` given the code point that follows it, and
+/// whether that following code point was a variation selector (U+FE0E or
+/// U+FE0F) consumed into `c`'s width. Shared by `str_width` and
+/// `width_prefix_len` so the two stay in lockstep.
+fn char_width_with_next(c: char, next: Option<char>, is_cjk: bool) -> (usize, bool) {
+    match next {
+        Some('\u{FE0E}') | Some('\u{FE0F}') => {
+            (cw::width_with_next(c, next, is_cjk).unwrap_or(0), true)
+        }
+        _ => (cw::width(c, is_cjk).unwrap_or(0), false),
+    }
+}
+
+/// Sums the displayed width of `s` in columns, with one code point of
+/// lookahead so that a base character followed by a variation selector
+/// (U+FE0E or U+FE0F) is measured according to the presentation it selects
+/// rather than its context-free width.
+fn str_width(s: &str, is_cjk: bool) -> usize {
+    let mut total = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().cloned();
+        let (w, consumed_next) = char_width_with_next(c, next, is_cjk);
+        total += w;
+        if consumed_next {
+            chars.next();
+        }
+    }
+    total
+}
+
+/// Configuration for how ambiguous-width characters are measured, passed to
+/// `width_with`.
+///
+/// This is the single extension point for context-dependent width rules:
+/// `width`/`width_cjk` are fixed shorthands for the two `ambiguous_is_wide`
+/// settings, while `width_with` leaves the choice to the caller and is
+/// where future flags (e.g. emoji presentation, grapheme clustering) will
+/// be added without multiplying method names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WidthConfig {
+    /// Treat characters in the East Asian Ambiguous category as 2 columns
+    /// wide (as recommended for CJK contexts) rather than 1 (as recommended
+    /// for non-CJK contexts, the default).
+    pub ambiguous_is_wide: bool,
+}
+
+impl Default for WidthConfig {
+    /// Returns the non-CJK default: Ambiguous characters are 1 column wide.
+    fn default() -> WidthConfig {
+        WidthConfig { ambiguous_is_wide: false }
+    }
+}
 
 /// Methods for determining displayed width of Unicode characters.
 #[allow(missing_docs)]
 pub trait UnicodeWidthChar {
     fn width(self) -> Option<usize>;
     fn width_cjk(self) -> Option<usize>;
+    fn width_with(self, opts: WidthConfig) -> Option<usize>;
 }
 
 impl UnicodeWidthChar for char {
@@ -69,7 +125,7 @@ impl UnicodeWidthChar for char {
     /// to [Unicode Standard Annex #11](http://www.unicode.org/reports/tr11/)
     /// as 1 column wide. This is consistent with the recommendations for non-CJK
     /// contexts, or when the context cannot be reliably determined.
-    fn width(self) -> Option<usize> { cw::width(self, false) }
+    fn width(self) -> Option<usize> { self.width_with(WidthConfig::default()) }
 
     /// Returns the character's displayed width in columns, or `None` if the
     /// character is a control character other than `'\x00'`.
@@ -78,7 +134,17 @@ impl UnicodeWidthChar for char {
     /// to [Unicode Standard Annex #11](http://www.unicode.org/reports/tr11/)
     /// as 2 columns wide. This is consistent with the recommendations for
     /// CJK contexts.
-    fn width_cjk(self) -> Option<usize> { cw::width(self, true) }
+    fn width_cjk(self) -> Option<usize> {
+        self.width_with(WidthConfig { ambiguous_is_wide: true })
+    }
+
+    /// Returns the character's displayed width in columns according to
+    /// `opts`, or `None` if the character is a control character other than
+    /// `'\x00'`. `width` and `width_cjk` are shorthands for the two
+    /// `ambiguous_is_wide` settings.
+    fn width_with(self, opts: WidthConfig) -> Option<usize> {
+        cw::width(self, opts.ambiguous_is_wide)
+    }
 }
 
 /// Methods for determining displayed width of Unicode strings.
@@ -86,34 +152,135 @@ impl UnicodeWidthChar for char {
 pub trait UnicodeWidthStr {
     fn width<'a>(&'a self) -> usize;
     fn width_cjk<'a>(&'a self) -> usize;
+    fn width_grapheme<'a>(&'a self) -> usize;
+    fn width_cjk_grapheme<'a>(&'a self) -> usize;
+    fn width_prefix_len<'a>(&'a self, max: usize) -> usize;
+    fn width_cjk_prefix_len<'a>(&'a self, max: usize) -> usize;
+    fn truncate_to_width<'a>(&'a self, max: usize) -> &'a str;
+    fn truncate_to_width_cjk<'a>(&'a self, max: usize) -> &'a str;
+    fn width_with<'a>(&'a self, opts: WidthConfig) -> usize;
 }
 
 impl UnicodeWidthStr for str {
     /// Returns the string's displayed width in columns.
     ///
-    /// Control characters are treated as having zero width.
+    /// Control characters are treated as having zero width. A character
+    /// followed by the emoji (U+FE0F) or text (U+FE0E) variation selector
+    /// is measured according to the presentation the selector requests,
+    /// where that character has a defined emoji presentation.
     ///
     /// This function treats characters in the Ambiguous category according
     /// to [Unicode Standard Annex #11](http://www.unicode.org/reports/tr11/)
     /// as 1 column wide. This is consistent with the recommendations for
     /// non-CJK contexts, or when the context cannot be reliably determined.
     fn width(&self) -> usize {
-        self.chars().map(|c| cw::width(c, false).unwrap_or(0)).sum()
+        self.width_with(WidthConfig::default())
     }
 
     /// Returns the string's displayed width in columns.
     ///
-    /// Control characters are treated as having zero width.
+    /// Control characters are treated as having zero width. A character
+    /// followed by the emoji (U+FE0F) or text (U+FE0E) variation selector
+    /// is measured according to the presentation the selector requests,
+    /// where that character has a defined emoji presentation.
     ///
     /// This function treats characters in the Ambiguous category according
     /// to [Unicode Standard Annex #11](http://www.unicode.org/reports/tr11/)
     /// as 2 column wide. This is consistent with the recommendations for
     /// CJK contexts.
     fn width_cjk(&self) -> usize {
-        self.chars().map(|c| cw::width(c, true).unwrap_or(0)).sum()
+        self.width_with(WidthConfig { ambiguous_is_wide: true })
+    }
+
+    /// Returns the string's displayed width in columns, measuring by
+    /// extended grapheme cluster rather than by individual `char`.
+    ///
+    /// Within each cluster, combining marks contribute no width, a pair of
+    /// regional indicators collapses to a single width-2 flag, and a
+    /// zero-width-joiner sequence collapses to the width of its first
+    /// emoji. This gives a more accurate column count for emoji sequences
+    /// and combining marks than [`width`](#tymethod.width).
+    ///
+    /// This function treats characters in the Ambiguous category according
+    /// to [Unicode Standard Annex #11](http://www.unicode.org/reports/tr11/)
+    /// as 1 column wide.
+    fn width_grapheme(&self) -> usize {
+        grapheme::width(self, false)
+    }
+
+    /// Returns the string's displayed width in columns, measuring by
+    /// extended grapheme cluster rather than by individual `char`.
+    ///
+    /// See [`width_grapheme`](#tymethod.width_grapheme) for the clustering
+    /// rules. This function treats characters in the Ambiguous category as
+    /// 2 columns wide, consistent with CJK contexts.
+    fn width_cjk_grapheme(&self) -> usize {
+        grapheme::width(self, true)
+    }
+
+    /// Returns the byte index of the longest prefix of `self` whose
+    /// displayed width is no more than `max` columns.
+    ///
+    /// The returned index always falls on a `char` boundary, and a
+    /// character is only included if it fits without exceeding `max`, so
+    /// the prefix never splits a wide character in two. If the very first
+    /// character is already wider than `max`, `0` is returned.
+    fn width_prefix_len(&self, max: usize) -> usize {
+        width_prefix_len(self, max, false)
+    }
+
+    /// Returns the byte index of the longest prefix of `self` whose
+    /// displayed width is no more than `max` columns, treating Ambiguous
+    /// characters as 2 columns wide as in [`width_cjk`](#tymethod.width_cjk).
+    fn width_cjk_prefix_len(&self, max: usize) -> usize {
+        width_prefix_len(self, max, true)
+    }
+
+    /// Returns the longest prefix of `self` whose displayed width is no
+    /// more than `max` columns.
+    ///
+    /// See [`width_prefix_len`](#tymethod.width_prefix_len) for how the
+    /// cutoff is chosen.
+    fn truncate_to_width(&self, max: usize) -> &str {
+        &self[..self.width_prefix_len(max)]
+    }
+
+    /// Returns the longest prefix of `self` whose displayed width is no
+    /// more than `max` columns, treating Ambiguous characters as 2 columns
+    /// wide as in [`width_cjk`](#tymethod.width_cjk).
+    fn truncate_to_width_cjk(&self, max: usize) -> &str {
+        &self[..self.width_cjk_prefix_len(max)]
+    }
+
+    /// Returns the string's displayed width in columns according to
+    /// `opts`. `width` and `width_cjk` are shorthands for the two
+    /// `ambiguous_is_wide` settings.
+    fn width_with(&self, opts: WidthConfig) -> usize {
+        str_width(self, opts.ambiguous_is_wide)
     }
 }
 
+/// Returns the byte index of the longest prefix of `s` whose accumulated
+/// width (using the same variation-selector-aware lookahead as `str_width`)
+/// does not exceed `max` columns, without ever splitting a character, or a
+/// character plus the variation selector that follows it, in two.
+fn width_prefix_len(s: &str, max: usize, is_cjk: bool) -> usize {
+    let mut total = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let (w, consumed_next) = char_width_with_next(c, next, is_cjk);
+        if total + w > max {
+            return idx;
+        }
+        total += w;
+        if consumed_next {
+            chars.next();
+        }
+    }
+    s.len()
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -130,6 +297,81 @@ mod tests {
         assert_eq!("\u{2081}\u{2082}\u{2083}\u{2084}".width_cjk(), 8);
     }
 
+    #[test]
+    fn test_str_grapheme() {
+        use super::UnicodeWidthStr;
+
+        // Regional indicator pair ("US" flag): two wide chars collapse to one.
+        assert_eq!("\u{1F1FA}\u{1F1F8}".width(), 4);
+        assert_eq!("\u{1F1FA}\u{1F1F8}".width_grapheme(), 2);
+
+        // ZWJ family emoji: three people joined by ZWJ collapse to the
+        // width of the first.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(family.width(), 8);
+        assert_eq!(family.width_grapheme(), 2);
+        assert_eq!(family.width_cjk_grapheme(), 2);
+
+        // Combining marks still count towards the plain, non-clustering
+        // `width`: only `width_grapheme` collapses them into the base.
+        assert_eq!("e\u{0301}".width(), 2);
+        assert_eq!("e\u{0301}".width_grapheme(), 1);
+    }
+
+    #[test]
+    fn test_str_variation_selector() {
+        use super::UnicodeWidthStr;
+
+        // Text-presentation-default "▶" forced wide by the emoji selector.
+        assert_eq!("\u{25B6}".width(), 1);
+        assert_eq!("\u{25B6}\u{FE0F}".width(), 2);
+
+        // Emoji-presentation-default "😀" forced narrow by the text selector.
+        assert_eq!("\u{1F600}".width(), 2);
+        assert_eq!("\u{1F600}\u{FE0E}".width(), 1);
+
+        // `width_grapheme` must agree with `width` on a VS-bearing base:
+        // the selector is part of the same cluster, not a separate one.
+        assert_eq!("\u{25B6}\u{FE0F}".width_grapheme(), 2);
+        assert_eq!("\u{1F600}\u{FE0E}".width_grapheme(), 1);
+
+        // Digit-keycap emoji ("1️⃣"): base digit + emoji selector + the
+        // combining enclosing keycap mark all collapse into one cluster.
+        assert_eq!("1\u{FE0F}\u{20E3}".width_grapheme(), 2);
+    }
+
+    #[test]
+    fn test_str_prefix() {
+        use super::UnicodeWidthStr;
+
+        assert_eq!("hello".width_prefix_len(3), 3);
+        assert_eq!("hello".truncate_to_width(3), "hel");
+        assert_eq!("hello".width_prefix_len(100), 5);
+
+        // Never split a wide character: "ｈｅｌｌｏ" is all width-2 chars,
+        // each 3 bytes, so a budget of 4 columns fits exactly two of them.
+        assert_eq!("ｈｅｌｌｏ".width_prefix_len(4), 6);
+        assert_eq!("ｈｅｌｌｏ".truncate_to_width(4), "ｈｅ");
+
+        // A budget that can't even fit the first (wide) character yields an
+        // empty prefix rather than splitting it.
+        assert_eq!("ｈｅｌｌｏ".width_prefix_len(1), 0);
+        assert_eq!("ｈｅｌｌｏ".truncate_to_width(1), "");
+
+        assert_eq!("\u{2081}\u{2082}".width_cjk_prefix_len(2), 3);
+        assert_eq!("\u{2081}\u{2082}".truncate_to_width_cjk(2), "\u{2081}");
+
+        // A base character plus its variation selector is one indivisible
+        // unit: it agrees with `.width()` and is never split or let through
+        // when it doesn't fit.
+        let triangle = "\u{25B6}\u{FE0F}";
+        assert_eq!(triangle.width(), 2);
+        assert_eq!(triangle.width_prefix_len(1), 0);
+        assert_eq!(triangle.truncate_to_width(1), "");
+        assert_eq!(triangle.width_prefix_len(2), triangle.len());
+        assert_eq!(triangle.truncate_to_width(2), triangle);
+    }
+
     #[test]
     fn test_char() {
         use super::UnicodeWidthChar;
@@ -144,4 +386,21 @@ mod tests {
         assert_eq!(UnicodeWidthChar::width('\u{2081}'), Some(1));
         assert_eq!('\u{2081}'.width_cjk(), Some(2));
     }
+
+    #[test]
+    fn test_width_with() {
+        use super::{UnicodeWidthChar, UnicodeWidthStr, WidthConfig};
+        use core::option::Option::Some;
+
+        let narrow = WidthConfig::default();
+        let wide = WidthConfig { ambiguous_is_wide: true };
+
+        assert_eq!('\u{2081}'.width_with(narrow), Some(1));
+        assert_eq!('\u{2081}'.width_with(wide), Some(2));
+        assert_eq!('\u{2081}'.width_with(narrow), '\u{2081}'.width());
+        assert_eq!('\u{2081}'.width_with(wide), '\u{2081}'.width_cjk());
+
+        assert_eq!("\u{2081}\u{2082}".width_with(narrow), 2);
+        assert_eq!("\u{2081}\u{2082}".width_with(wide), 4);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,204 @@
+// Copyright 2012-2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tables describing the width and other Unicode properties of code points.
+//!
+//! The ranges below are derived from `EastAsianWidth.txt`,
+//! `UnicodeData.txt` and the emoji data files as published by the Unicode
+//! Consortium, and are kept
+//! sorted and non-overlapping so that they can be searched with
+//! `bsearch_range_table`.
+
+/// The version of Unicode that this version of unicode-width is based on.
+pub const UNICODE_VERSION: (u64, u64, u64) = (8, 0, 0);
+
+/// Functions for computing the width of a `char`.
+pub mod charwidth {
+    use core::cmp::Ordering;
+    use core::option::Option::{self, None, Some};
+
+    fn bsearch_range_table(c: char, table: &'static [(char, char)]) -> bool {
+        table.binary_search_by(|&(lo, hi)| {
+            if c < lo {
+                Ordering::Greater
+            } else if hi < c {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            }
+        }).is_ok()
+    }
+
+    // East Asian Wide (W) and Fullwidth (F) code points.
+    static WIDE: &'static [(char, char)] = &[
+        ('\u{1100}', '\u{115F}'),
+        ('\u{2E80}', '\u{303E}'),
+        ('\u{3041}', '\u{33FF}'),
+        ('\u{3400}', '\u{4DBF}'),
+        ('\u{4E00}', '\u{9FFF}'),
+        ('\u{A000}', '\u{A4CF}'),
+        ('\u{AC00}', '\u{D7A3}'),
+        ('\u{F900}', '\u{FAFF}'),
+        ('\u{FF00}', '\u{FF60}'),
+        ('\u{FFE0}', '\u{FFE6}'),
+        ('\u{1F1E6}', '\u{1F1FF}'),
+        ('\u{1F300}', '\u{1F64F}'),
+        ('\u{1F900}', '\u{1F9FF}'),
+        ('\u{20000}', '\u{2FFFD}'),
+        ('\u{30000}', '\u{3FFFD}'),
+    ];
+
+    // East Asian Ambiguous (A) code points: rendered as width 1 in non-CJK
+    // contexts and width 2 in CJK contexts.
+    static AMBIGUOUS: &'static [(char, char)] = &[
+        ('\u{00A1}', '\u{00A1}'),
+        ('\u{00A4}', '\u{00A4}'),
+        ('\u{00A7}', '\u{00A8}'),
+        ('\u{00AA}', '\u{00AA}'),
+        ('\u{00AE}', '\u{00AE}'),
+        ('\u{2010}', '\u{2010}'),
+        ('\u{2013}', '\u{2016}'),
+        ('\u{2018}', '\u{2019}'),
+        ('\u{201C}', '\u{201D}'),
+        ('\u{2020}', '\u{2022}'),
+        ('\u{2030}', '\u{2030}'),
+        ('\u{2032}', '\u{2033}'),
+        ('\u{2035}', '\u{2035}'),
+        ('\u{203B}', '\u{203B}'),
+        ('\u{2080}', '\u{2089}'),
+        ('\u{2103}', '\u{2103}'),
+        ('\u{2160}', '\u{216B}'),
+        ('\u{2170}', '\u{2179}'),
+        ('\u{2190}', '\u{2199}'),
+        ('\u{2460}', '\u{24FF}'),
+        ('\u{25A0}', '\u{25FB}'),
+        ('\u{2600}', '\u{266F}'),
+    ];
+
+    // Combining marks (general categories Mn and Me), zero-width spaces and
+    // joiners, and variation selectors. `cw::width` measures these the same
+    // as any other character; it is only within `grapheme::width`'s
+    // clustering (via `is_combining_mark`) that they contribute no width of
+    // their own when they follow a base character.
+    static ZERO_WIDTH: &'static [(char, char)] = &[
+        ('\u{0300}', '\u{036F}'),
+        ('\u{0483}', '\u{0489}'),
+        ('\u{0591}', '\u{05BD}'),
+        ('\u{05BF}', '\u{05BF}'),
+        ('\u{05C1}', '\u{05C2}'),
+        ('\u{05C4}', '\u{05C5}'),
+        ('\u{05C7}', '\u{05C7}'),
+        ('\u{0610}', '\u{061A}'),
+        ('\u{064B}', '\u{065F}'),
+        ('\u{0670}', '\u{0670}'),
+        ('\u{06D6}', '\u{06DC}'),
+        ('\u{06DF}', '\u{06E4}'),
+        ('\u{0E31}', '\u{0E31}'),
+        ('\u{0E34}', '\u{0E3A}'),
+        ('\u{200B}', '\u{200D}'),
+        ('\u{20D0}', '\u{20FF}'),
+        ('\u{FE00}', '\u{FE0F}'),
+        ('\u{FE20}', '\u{FE2F}'),
+    ];
+
+    // The regional indicator symbols, U+1F1E6..=U+1F1FF, used in pairs to
+    // spell out flag emoji.
+    const REGIONAL_INDICATOR: (char, char) = ('\u{1F1E6}', '\u{1F1FF}');
+
+    // Code points with Emoji_Presentation=Yes in the Unicode emoji data:
+    // these default to wide emoji-style rendering, but U+FE0E forces
+    // narrow text presentation.
+    static EMOJI_PRESENTATION_DEFAULT: &'static [(char, char)] = &[
+        ('\u{1F300}', '\u{1F64F}'),
+        ('\u{1F900}', '\u{1F9FF}'),
+    ];
+
+    // Code points with Emoji=Yes but Emoji_Presentation=No in the Unicode
+    // emoji data: these default to narrow text rendering, but U+FE0F forces
+    // wide emoji presentation.
+    static TEXT_PRESENTATION_DEFAULT: &'static [(char, char)] = &[
+        ('\u{0023}', '\u{0023}'),
+        ('\u{002A}', '\u{002A}'),
+        ('\u{0030}', '\u{0039}'),
+        ('\u{25B6}', '\u{25B6}'),
+        ('\u{25C0}', '\u{25C0}'),
+        ('\u{2600}', '\u{2604}'),
+        ('\u{260E}', '\u{260E}'),
+        ('\u{2611}', '\u{2611}'),
+        ('\u{2614}', '\u{2615}'),
+        ('\u{2648}', '\u{2653}'),
+        ('\u{2660}', '\u{2660}'),
+        ('\u{2663}', '\u{2663}'),
+        ('\u{2665}', '\u{2666}'),
+        ('\u{2668}', '\u{2668}'),
+        ('\u{267B}', '\u{267B}'),
+        ('\u{2699}', '\u{2699}'),
+        ('\u{26A0}', '\u{26A1}'),
+        ('\u{26AA}', '\u{26AB}'),
+        ('\u{26BD}', '\u{26BE}'),
+        ('\u{2708}', '\u{2709}'),
+        ('\u{2764}', '\u{2764}'),
+    ];
+
+    /// The zero-width joiner, U+200D, which glues adjacent emoji into a
+    /// single displayed cluster.
+    pub const ZWJ: char = '\u{200D}';
+
+    /// Returns `true` if `c` is a combining mark, zero-width space, or
+    /// variation selector that contributes no width of its own within a
+    /// grapheme cluster.
+    pub fn is_combining_mark(c: char) -> bool {
+        bsearch_range_table(c, ZERO_WIDTH)
+    }
+
+    /// Returns `true` if `c` is one of the regional indicator symbols used
+    /// to compose flag emoji.
+    pub fn is_regional_indicator(c: char) -> bool {
+        let (lo, hi) = REGIONAL_INDICATOR;
+        lo <= c && c <= hi
+    }
+
+    /// Returns the character's displayed width in columns, or `None` if the
+    /// character is a control character other than `'\x00'`.
+    pub fn width(c: char, is_cjk: bool) -> Option<usize> {
+        match c {
+            '\x00' => Some(0),
+            c if (c as u32) < 0x20 => None,
+            c if (c as u32) < 0x7f => Some(1),
+            c if (c as u32) < 0xa0 => None,
+            c if bsearch_range_table(c, WIDE) => Some(2),
+            c if is_cjk && bsearch_range_table(c, AMBIGUOUS) => Some(2),
+            _ => Some(1),
+        }
+    }
+
+    /// Returns `true` if `c` has a defined emoji presentation (default or
+    /// not), meaning a following variation selector changes its width.
+    fn has_emoji_presentation(c: char) -> bool {
+        bsearch_range_table(c, EMOJI_PRESENTATION_DEFAULT)
+            || bsearch_range_table(c, TEXT_PRESENTATION_DEFAULT)
+    }
+
+    /// Returns the displayed width of `c` in columns, taking into account
+    /// the code point that follows it.
+    ///
+    /// A text-presentation-default character followed by U+FE0F (emoji
+    /// variation selector) is measured as its emoji-presentation width (2);
+    /// an emoji-presentation-capable character followed by U+FE0E (text
+    /// variation selector) is measured as its text-presentation width (1).
+    /// Otherwise this falls back to [`width`](fn.width.html).
+    pub fn width_with_next(c: char, next: Option<char>, is_cjk: bool) -> Option<usize> {
+        match next {
+            Some('\u{FE0F}') if has_emoji_presentation(c) => Some(2),
+            Some('\u{FE0E}') if has_emoji_presentation(c) => Some(1),
+            _ => width(c, is_cjk),
+        }
+    }
+}